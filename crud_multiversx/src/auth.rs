@@ -0,0 +1,89 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Guards the mutating routes with an HMAC-SHA256 over the raw request
+/// body, keyed by a secret shared out of band with clients.
+#[derive(Clone)]
+pub struct AuthConfig {
+    secret: Vec<u8>,
+}
+
+impl AuthConfig {
+    /// Loads the shared secret from `API_SECRET`, or generates a random one
+    /// and prints it once so an operator can copy it into a client config.
+    pub fn from_env() -> Self {
+        let secret = match std::env::var("API_SECRET") {
+            Ok(s) => s.into_bytes(),
+            Err(_) => {
+                let generated = generate_secret();
+                println!(
+                    "Nenhum API_SECRET definido; gerando um segredo novo: {}",
+                    hex::encode(&generated)
+                );
+                generated
+            }
+        };
+        AuthConfig { secret }
+    }
+
+    /// Verifies the `Authorization: Bearer <hex hmac>` header against the
+    /// HMAC-SHA256 of `body` computed with the shared secret. The
+    /// comparison is constant-time (`Mac::verify_slice`) to avoid timing
+    /// leaks, and must run before `body` is parsed as JSON.
+    pub fn verify(&self, header: Option<&str>, body: &[u8]) -> bool {
+        let Some(header) = header else { return false };
+        let Some(provided_hex) = header.strip_prefix("Bearer ") else { return false };
+        let Ok(provided) = hex::decode(provided_hex) else { return false };
+
+        let Ok(mut mac) = HmacSha256::new_from_slice(&self.secret) else { return false };
+        mac.update(body);
+        mac.verify_slice(&provided).is_ok()
+    }
+}
+
+fn generate_secret() -> Vec<u8> {
+    use rand::RngCore;
+    let mut bytes = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret).unwrap();
+        mac.update(body);
+        format!("Bearer {}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn verify_accepts_a_correctly_signed_body() {
+        let auth = AuthConfig { secret: b"segredo".to_vec() };
+        let body = br#"{"name":"Ana","age":30}"#;
+        let header = sign(b"segredo", body);
+
+        assert!(auth.verify(Some(&header), body));
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_body() {
+        let auth = AuthConfig { secret: b"segredo".to_vec() };
+        let header = sign(b"segredo", br#"{"name":"Ana","age":30}"#);
+
+        assert!(!auth.verify(Some(&header), br#"{"name":"Ana","age":99}"#));
+    }
+
+    #[test]
+    fn verify_rejects_missing_or_malformed_header() {
+        let auth = AuthConfig { secret: b"segredo".to_vec() };
+        let body = b"qualquer coisa";
+
+        assert!(!auth.verify(None, body));
+        assert!(!auth.verify(Some("Bearer not-hex"), body));
+        assert!(!auth.verify(Some("Basic dXNlcjpwYXNz"), body));
+    }
+}
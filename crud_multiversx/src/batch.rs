@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Person;
+
+/// A single operation within a `POST /persons/batch` request.
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    Create { data: Person },
+    Update { id: u64, data: Person },
+    Delete { id: u64 },
+}
+
+/// The effect of one successfully-applied batch operation.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum BatchOutcome {
+    Created { person: Person },
+    Updated { person: Person },
+    Deleted { id: u64 },
+}
+
+/// Returned when a batch can't be applied atomically: the index of the
+/// offending operation and why it was rejected. No operation in the batch
+/// is applied when this is returned.
+#[derive(Debug)]
+pub struct BatchError {
+    pub index: usize,
+    pub message: String,
+}
+
+/// The per-operation result reported back to the client: one entry per
+/// operation in the request, in the same order, whether the batch
+/// succeeded or was rolled back.
+#[derive(Serialize)]
+#[serde(tag = "result", rename_all = "lowercase")]
+pub enum BatchItemResult {
+    Ok { outcome: BatchOutcome },
+    Error { message: String },
+    Skipped { reason: String },
+}
+
+impl BatchItemResult {
+    /// Builds the full, same-length result array from a `Storage::apply_batch`
+    /// outcome: every operation gets an entry, even the ones skipped because
+    /// another operation in the same batch failed and rolled everything back.
+    pub fn from_batch(op_count: usize, result: Result<Vec<BatchOutcome>, BatchError>) -> Vec<BatchItemResult> {
+        match result {
+            Ok(outcomes) => outcomes
+                .into_iter()
+                .map(|outcome| BatchItemResult::Ok { outcome })
+                .collect(),
+            Err(BatchError { index, message }) => (0..op_count)
+                .map(|i| {
+                    if i == index {
+                        BatchItemResult::Error { message: message.clone() }
+                    } else {
+                        BatchItemResult::Skipped {
+                            reason: "lote revertido devido a uma operação inválida".to_string(),
+                        }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hyper::{Body, Response};
+
+/// Responses smaller than this aren't worth the CPU cost of gzip.
+const COMPRESSION_THRESHOLD_BYTES: usize = 256;
+
+/// Gzip-compresses `response`'s body when the client advertised gzip
+/// support (`Accept-Encoding`) and the body is large enough to benefit.
+/// Streaming responses (e.g. the SSE change-feed) are passed through
+/// untouched: buffering a body that only ends when the client disconnects
+/// would hang forever instead of compressing it.
+pub async fn maybe_compress(response: Response<Body>, accept_encoding: Option<&str>) -> Response<Body> {
+    let is_streaming = response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if is_streaming {
+        return response;
+    }
+
+    let accepts_gzip = accept_encoding.is_some_and(|value| value.contains("gzip"));
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+
+    if !accepts_gzip || bytes.len() < COMPRESSION_THRESHOLD_BYTES {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed = match encoder.write_all(&bytes).and_then(|_| encoder.finish()) {
+        Ok(compressed) => compressed,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+
+    parts.headers.insert(
+        hyper::header::CONTENT_ENCODING,
+        hyper::header::HeaderValue::from_static("gzip"),
+    );
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(compressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_response(body: &str) -> Response<Body> {
+        Response::builder()
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn sse_response() -> Response<Body> {
+        Response::builder()
+            .header(hyper::header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::wrap_stream(tokio_stream::once(
+                Ok::<_, std::convert::Infallible>("event: created\ndata: {}\n\n"),
+            )))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn leaves_small_bodies_uncompressed_even_when_gzip_is_accepted() {
+        let response = json_response("{}");
+        let result = maybe_compress(response, Some("gzip")).await;
+
+        assert!(result.headers().get(hyper::header::CONTENT_ENCODING).is_none());
+        let bytes = hyper::body::to_bytes(result.into_body()).await.unwrap();
+        assert_eq!(&bytes[..], b"{}");
+    }
+
+    #[tokio::test]
+    async fn leaves_large_bodies_uncompressed_when_client_does_not_accept_gzip() {
+        let body = "x".repeat(COMPRESSION_THRESHOLD_BYTES + 1);
+        let response = json_response(&body);
+        let result = maybe_compress(response, None).await;
+
+        assert!(result.headers().get(hyper::header::CONTENT_ENCODING).is_none());
+        let bytes = hyper::body::to_bytes(result.into_body()).await.unwrap();
+        assert_eq!(bytes.len(), body.len());
+    }
+
+    #[tokio::test]
+    async fn gzips_large_bodies_when_the_client_accepts_gzip() {
+        let body = "x".repeat(COMPRESSION_THRESHOLD_BYTES + 1);
+        let response = json_response(&body);
+        let result = maybe_compress(response, Some("gzip, deflate")).await;
+
+        assert_eq!(
+            result.headers().get(hyper::header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        let bytes = hyper::body::to_bytes(result.into_body()).await.unwrap();
+        assert!(bytes.len() < body.len());
+    }
+
+    #[tokio::test]
+    async fn passes_streaming_responses_through_untouched() {
+        let response = sse_response();
+        let result = maybe_compress(response, Some("gzip")).await;
+
+        assert!(result.headers().get(hyper::header::CONTENT_ENCODING).is_none());
+        let bytes = hyper::body::to_bytes(result.into_body()).await.unwrap();
+        assert_eq!(&bytes[..], b"event: created\ndata: {}\n\n");
+    }
+}
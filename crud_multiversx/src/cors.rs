@@ -0,0 +1,132 @@
+use hyper::header::HeaderValue;
+use hyper::{Body, Response, StatusCode};
+
+/// Startup-configured CORS policy. Origins, methods and headers each come
+/// from a comma-separated env var, defaulting to "allow everything" so the
+/// API works out of the box for browser clients.
+pub struct CorsConfig {
+    allowed_origins: Vec<String>,
+    allowed_methods: String,
+    allowed_headers: String,
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        CorsConfig {
+            allowed_origins: std::env::var("CORS_ALLOWED_ORIGINS")
+                .unwrap_or_else(|_| "*".to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect(),
+            allowed_methods: std::env::var("CORS_ALLOWED_METHODS")
+                .unwrap_or_else(|_| "GET,POST,PUT,DELETE,OPTIONS".to_string()),
+            allowed_headers: std::env::var("CORS_ALLOWED_HEADERS")
+                .unwrap_or_else(|_| "Content-Type,Authorization".to_string()),
+        }
+    }
+
+    fn allow_origin_value(&self, origin: Option<&str>) -> Option<String> {
+        if self.allowed_origins.iter().any(|o| o == "*") {
+            return Some("*".to_string());
+        }
+        let origin = origin?;
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == origin)
+            .then(|| origin.to_string())
+    }
+
+    /// Adds `Access-Control-Allow-Origin` to an already-built response,
+    /// when the requester's `Origin` is permitted.
+    pub fn apply(&self, response: &mut Response<Body>, origin: Option<&str>) {
+        if let Some(allow_origin) = self.allow_origin_value(origin) {
+            if let Ok(value) = HeaderValue::from_str(&allow_origin) {
+                response.headers_mut().insert("access-control-allow-origin", value);
+            }
+        }
+    }
+
+    /// Builds the `204 No Content` reply to an `OPTIONS` preflight request.
+    pub fn preflight_response(&self, origin: Option<&str>) -> Response<Body> {
+        let mut response = Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap();
+
+        self.apply(&mut response, origin);
+        if let Ok(methods) = HeaderValue::from_str(&self.allowed_methods) {
+            response.headers_mut().insert("access-control-allow-methods", methods);
+        }
+        if let Ok(headers) = HeaderValue::from_str(&self.allowed_headers) {
+            response.headers_mut().insert("access-control-allow-headers", headers);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(allowed_origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: allowed_origins.iter().map(|s| s.to_string()).collect(),
+            allowed_methods: "GET,POST,PUT,DELETE,OPTIONS".to_string(),
+            allowed_headers: "Content-Type,Authorization".to_string(),
+        }
+    }
+
+    #[test]
+    fn allow_origin_value_wildcard_allows_any_origin() {
+        let cors = config(&["*"]);
+        assert_eq!(
+            cors.allow_origin_value(Some("https://example.com")),
+            Some("*".to_string())
+        );
+        assert_eq!(cors.allow_origin_value(None), Some("*".to_string()));
+    }
+
+    #[test]
+    fn allow_origin_value_echoes_an_allow_listed_origin() {
+        let cors = config(&["https://a.com", "https://b.com"]);
+        assert_eq!(
+            cors.allow_origin_value(Some("https://b.com")),
+            Some("https://b.com".to_string())
+        );
+    }
+
+    #[test]
+    fn allow_origin_value_rejects_an_origin_not_on_the_list() {
+        let cors = config(&["https://a.com"]);
+        assert_eq!(cors.allow_origin_value(Some("https://evil.com")), None);
+        assert_eq!(cors.allow_origin_value(None), None);
+    }
+
+    #[test]
+    fn preflight_response_sets_cors_headers_and_no_content_status() {
+        let cors = config(&["https://a.com"]);
+        let response = cors.preflight_response(Some("https://a.com"));
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            response.headers().get("access-control-allow-origin").unwrap(),
+            "https://a.com"
+        );
+        assert_eq!(
+            response.headers().get("access-control-allow-methods").unwrap(),
+            "GET,POST,PUT,DELETE,OPTIONS"
+        );
+        assert_eq!(
+            response.headers().get("access-control-allow-headers").unwrap(),
+            "Content-Type,Authorization"
+        );
+    }
+
+    #[test]
+    fn preflight_response_omits_allow_origin_for_a_disallowed_origin() {
+        let cors = config(&["https://a.com"]);
+        let response = cors.preflight_response(Some("https://evil.com"));
+
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+    }
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::Person;
+
+/// Capacity of the broadcast channel backing the SSE feed. Subscribers that
+/// fall behind this many events lose the oldest ones rather than blocking
+/// writers (`tokio::sync::broadcast` drop-oldest semantics).
+const CHANGE_FEED_CAPACITY: usize = 256;
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PersonEvent {
+    Created { person: Person },
+    Updated { person: Person },
+    Deleted { id: u64 },
+}
+
+/// Publishes person mutations to any number of SSE subscribers.
+#[derive(Clone)]
+pub struct ChangeFeed {
+    sender: broadcast::Sender<PersonEvent>,
+}
+
+impl ChangeFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANGE_FEED_CAPACITY);
+        ChangeFeed { sender }
+    }
+
+    pub fn publish(&self, event: PersonEvent) {
+        // No subscribers is not an error: the feed is best-effort.
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PersonEvent> {
+        self.sender.subscribe()
+    }
+}
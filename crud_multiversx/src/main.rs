@@ -1,29 +1,144 @@
+mod auth;
+mod batch;
+mod compression;
+mod cors;
+mod events;
+mod metrics;
+mod query;
+mod storage;
+
 use std::convert::Infallible;
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use hyper::{Body, Request, Response, Server, Method, StatusCode};
 use hyper::service::{make_service_fn, service_fn};
 
 use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use auth::AuthConfig;
+use batch::{BatchItemResult, BatchOp, BatchOutcome};
+use cors::CorsConfig;
+use events::{ChangeFeed, PersonEvent};
+use metrics::Metrics;
+use query::PersonQuery;
+use storage::Storage;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct Person {
     id: u64,
     name: String,
     age: u32,
 }
 
-type Db = Arc<Mutex<HashMap<u64, Person>>>;
+/// Response envelope for the paginated `GET /persons` listing.
+#[derive(Serialize)]
+struct PersonPage {
+    items: Vec<Person>,
+    total: usize,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+#[derive(Clone)]
+struct AppState {
+    db: Arc<dyn Storage>,
+    feed: ChangeFeed,
+    auth: AuthConfig,
+    metrics: Arc<Metrics>,
+    cors: Arc<CorsConfig>,
+}
+
+fn event_for_outcome(outcome: &BatchOutcome) -> PersonEvent {
+    match outcome.clone() {
+        BatchOutcome::Created { person } => PersonEvent::Created { person },
+        BatchOutcome::Updated { person } => PersonEvent::Updated { person },
+        BatchOutcome::Deleted { id } => PersonEvent::Deleted { id },
+    }
+}
+
+/// Serializes a `PersonEvent` as a named SSE frame, e.g.
+/// `event: created\ndata: {...}\n\n`.
+fn sse_frame(event: &PersonEvent) -> String {
+    let name = match event {
+        PersonEvent::Created { .. } => "created",
+        PersonEvent::Updated { .. } => "updated",
+        PersonEvent::Deleted { .. } => "deleted",
+    };
+    let data = serde_json::to_string(event).unwrap();
+    format!("event: {}\ndata: {}\n\n", name, data)
+}
+
+/// `GET /persons/events`: streams person mutations as Server-Sent Events.
+/// A periodic `: keep-alive` comment keeps idle proxies from closing the
+/// connection; if this subscriber falls behind it silently skips the
+/// events it missed instead of stalling the channel for everyone else.
+async fn sse_handler(feed: ChangeFeed) -> Result<Response<Body>, Infallible> {
+    let mut rx = feed.subscribe();
+    let (tx, body_rx) = mpsc::channel::<Result<String, Infallible>>(16);
+
+    tokio::spawn(async move {
+        let mut keep_alive = tokio::time::interval(Duration::from_secs(15));
+        loop {
+            tokio::select! {
+                event = rx.recv() => {
+                    match event {
+                        Ok(event) => {
+                            if tx.send(Ok(sse_frame(&event))).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+                _ = keep_alive.tick() => {
+                    if tx.send(Ok(": keep-alive\n\n".to_string())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Response::builder()
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(Body::wrap_stream(ReceiverStream::new(body_rx)))
+        .unwrap())
+}
 
-async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible> {
+async fn router(req: Request<Body>, state: AppState) -> Result<Response<Body>, Infallible> {
+    let db = state.db.clone();
     let path = req.uri().path().to_string();
+    let query_string = req.uri().query().map(str::to_string);
     let method = req.method().clone();
 
+    // Mutating routes require a valid HMAC over the raw body; this check
+    // (and the body read it needs) happens before any JSON parsing.
+    let is_mutating = matches!(method, Method::POST | Method::PUT | Method::DELETE);
+    let auth_header = req
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let whole_body = if is_mutating {
+        hyper::body::to_bytes(req.into_body()).await.unwrap()
+    } else {
+        hyper::body::Bytes::new()
+    };
+    if is_mutating && !state.auth.verify(auth_header.as_deref(), &whole_body) {
+        return Ok(Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::from("Não autorizado"))
+            .unwrap());
+    }
+
     match (method, path.as_str()) {
         (Method::POST, "/persons") => {
-            let whole_body = hyper::body::to_bytes(req.into_body()).await.unwrap();
-            let mut new_person: Person = match serde_json::from_slice(&whole_body) {
+            let new_person: Person = match serde_json::from_slice(&whole_body) {
                 Ok(p) => p,
                 Err(_) => {
                     return Ok(Response::builder()
@@ -33,31 +148,59 @@ async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible
                 }
             };
 
-            let mut db_lock = db.lock().unwrap();
-            let new_id = if db_lock.is_empty() {
-                1
-            } else {
-                db_lock.keys().max().unwrap() + 1
-            };
-            new_person.id = new_id;
-            db_lock.insert(new_id, new_person.clone());
+            let created = db.create(new_person);
+            state.feed.publish(PersonEvent::Created { person: created.clone() });
 
-            let json = serde_json::to_string(&new_person).unwrap();
+            let json = serde_json::to_string(&created).unwrap();
             Ok(Response::new(Body::from(json)))
         },
+        (Method::POST, "/persons/batch") => {
+            let ops: Vec<BatchOp> = match serde_json::from_slice(&whole_body) {
+                Ok(ops) => ops,
+                Err(_) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from("JSON inválido"))
+                        .unwrap());
+                }
+            };
+
+            let op_count = ops.len();
+            let result = db.apply_batch(&ops);
+            let status = if result.is_ok() { StatusCode::OK } else { StatusCode::CONFLICT };
+            if let Ok(outcomes) = &result {
+                for outcome in outcomes {
+                    state.feed.publish(event_for_outcome(outcome));
+                }
+            }
+
+            let items = BatchItemResult::from_batch(op_count, result);
+            let json = serde_json::to_string(&items).unwrap();
+            Ok(Response::builder().status(status).body(Body::from(json)).unwrap())
+        },
         (Method::GET, "/persons") => {
-            let db_lock = db.lock().unwrap();
-            let persons: Vec<&Person> = db_lock.values().collect();
-            let json = serde_json::to_string(&persons).unwrap();
+            let query = PersonQuery::parse(query_string.as_deref());
+            let (items, total) = query::apply(db.list(), &query);
+            let page = PersonPage {
+                items,
+                total,
+                limit: query.limit,
+                offset: query.offset,
+            };
+            let json = serde_json::to_string(&page).unwrap();
             Ok(Response::new(Body::from(json)))
         },
+        (Method::GET, "/persons/events") => sse_handler(state.feed.clone()).await,
+        (Method::GET, "/metrics") => Ok(Response::builder()
+            .header("content-type", "text/plain; version=0.0.4")
+            .body(Body::from(state.metrics.render(db.as_ref())))
+            .unwrap()),
         (Method::GET, path) if path.starts_with("/persons/") => {
             let id_str = path.trim_start_matches("/persons/");
             match id_str.parse::<u64>() {
                 Ok(id) => {
-                    let db_lock = db.lock().unwrap();
-                    if let Some(person) = db_lock.get(&id) {
-                        let json = serde_json::to_string(person).unwrap();
+                    if let Some(person) = db.get(id) {
+                        let json = serde_json::to_string(&person).unwrap();
                         Ok(Response::new(Body::from(json)))
                     } else {
                         Ok(Response::builder()
@@ -78,7 +221,6 @@ async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible
             let id_str = path.trim_start_matches("/persons/");
             match id_str.parse::<u64>() {
                 Ok(id) => {
-                    let whole_body = hyper::body::to_bytes(req.into_body()).await.unwrap();
                     let updated_data: Person = match serde_json::from_slice(&whole_body) {
                         Ok(p) => p,
                         Err(_) => {
@@ -89,11 +231,9 @@ async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible
                         }
                     };
 
-                    let mut db_lock = db.lock().unwrap();
-                    if let Some(person) = db_lock.get_mut(&id) {
-                        person.name = updated_data.name;
-                        person.age = updated_data.age;
-                        let json = serde_json::to_string(person).unwrap();
+                    if let Some(updated) = db.update(id, updated_data) {
+                        state.feed.publish(PersonEvent::Updated { person: updated.clone() });
+                        let json = serde_json::to_string(&updated).unwrap();
                         Ok(Response::new(Body::from(json)))
                     } else {
                         Ok(Response::builder()
@@ -114,8 +254,8 @@ async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible
             let id_str = path.trim_start_matches("/persons/");
             match id_str.parse::<u64>() {
                 Ok(id) => {
-                    let mut db_lock = db.lock().unwrap();
-                    if db_lock.remove(&id).is_some() {
+                    if db.delete(id) {
+                        state.feed.publish(PersonEvent::Deleted { id });
                         Ok(Response::new(Body::from("Pessoa removida")))
                     } else {
                         Ok(Response::builder()
@@ -141,15 +281,70 @@ async fn router(req: Request<Body>, db: Db) -> Result<Response<Body>, Infallible
     }
 }
 
+/// Wraps `router` with request metrics and structured access logging: start
+/// time and the in-flight gauge are recorded before dispatch, and the
+/// counters/histogram/log line are written once the response is known.
+async fn instrumented_router(req: Request<Body>, state: AppState) -> Result<Response<Body>, Infallible> {
+    let method = req.method().as_str().to_string();
+    let route = metrics::route_label(req.uri().path());
+    state.metrics.request_started(&method, route);
+
+    let start = Instant::now();
+    let response = router(req, state.clone()).await;
+    let elapsed = start.elapsed();
+
+    let status = response.as_ref().map(|r| r.status().as_u16()).unwrap_or(500);
+    state.metrics.request_finished(&method, route, status, elapsed);
+    println!(
+        "method={} route={} status={} latency_ms={}",
+        method,
+        route,
+        status,
+        elapsed.as_millis()
+    );
+
+    response
+}
+
+/// Outermost layer: answers CORS preflight directly, then composes CORS
+/// response headers and gzip compression around `instrumented_router`,
+/// mirroring how the Cozo server stacks its `CorsLayer`/`CompressionLayer`.
+async fn handle(req: Request<Body>, state: AppState) -> Result<Response<Body>, Infallible> {
+    let origin = req
+        .headers()
+        .get(hyper::header::ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let accept_encoding = req
+        .headers()
+        .get(hyper::header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    if req.method() == Method::OPTIONS {
+        return Ok(state.cors.preflight_response(origin.as_deref()));
+    }
+
+    let mut response = instrumented_router(req, state.clone()).await?;
+    state.cors.apply(&mut response, origin.as_deref());
+    Ok(compression::maybe_compress(response, accept_encoding.as_deref()).await)
+}
+
 #[tokio::main]
 async fn main() {
-    let db: Db = Arc::new(Mutex::new(HashMap::new()));
+    let state = AppState {
+        db: Arc::from(storage::from_env()),
+        feed: ChangeFeed::new(),
+        auth: AuthConfig::from_env(),
+        metrics: Arc::new(Metrics::new()),
+        cors: Arc::new(CorsConfig::from_env()),
+    };
 
     let make_svc = make_service_fn(|_conn| {
-        let db = db.clone();
+        let state = state.clone();
         async move {
             Ok::<_, Infallible>(service_fn(move |req| {
-                router(req, db.clone())
+                handle(req, state.clone())
             }))
         }
     });
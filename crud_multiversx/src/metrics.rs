@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::storage::Storage;
+
+const LATENCY_BUCKETS_SECONDS: &[f64] =
+    &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A cumulative latency histogram, matching Prometheus's `le` bucket
+/// semantics: `bucket_counts[i]` holds the count of observations with a
+/// duration <= `LATENCY_BUCKETS_SECONDS[i]`, and the last slot is `+Inf`.
+struct Histogram {
+    bucket_counts: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            bucket_counts: (0..=LATENCY_BUCKETS_SECONDS.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (i, bound) in LATENCY_BUCKETS_SECONDS.iter().enumerate() {
+            if seconds <= *bound {
+                self.bucket_counts[i].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.bucket_counts[LATENCY_BUCKETS_SECONDS.len()].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// In-process Prometheus counters and histograms for the HTTP layer.
+/// Hand-rolled rather than pulled from a metrics crate, since rendering
+/// plain text-format output doesn't need a full framework.
+pub struct Metrics {
+    requests_total: Mutex<HashMap<(String, String), u64>>,
+    responses_total: Mutex<HashMap<(String, String, u16), u64>>,
+    latency_by_route: Mutex<HashMap<String, Histogram>>,
+    in_flight: AtomicI64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            requests_total: Mutex::new(HashMap::new()),
+            responses_total: Mutex::new(HashMap::new()),
+            latency_by_route: Mutex::new(HashMap::new()),
+            in_flight: AtomicI64::new(0),
+        }
+    }
+
+    pub fn request_started(&self, method: &str, route: &str) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        *self
+            .requests_total
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string()))
+            .or_insert(0) += 1;
+    }
+
+    pub fn request_finished(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        *self
+            .responses_total
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert(0) += 1;
+        self.latency_by_route
+            .lock()
+            .unwrap()
+            .entry(route.to_string())
+            .or_insert_with(Histogram::new)
+            .observe(duration);
+    }
+
+    /// Renders all metrics as Prometheus text-format exposition, including
+    /// the current person count read live from `storage`.
+    pub fn render(&self, storage: &dyn Storage) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP crud_multiversx_requests_total Total requests received, by method and route.\n");
+        out.push_str("# TYPE crud_multiversx_requests_total counter\n");
+        for ((method, route), count) in self.requests_total.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "crud_multiversx_requests_total{{method=\"{}\",route=\"{}\"}} {}\n",
+                method, route, count
+            ));
+        }
+
+        out.push_str("# HELP crud_multiversx_responses_total Total responses sent, by method, route and status.\n");
+        out.push_str("# TYPE crud_multiversx_responses_total counter\n");
+        for ((method, route, status), count) in self.responses_total.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "crud_multiversx_responses_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}\n",
+                method, route, status, count
+            ));
+        }
+
+        out.push_str("# HELP crud_multiversx_requests_in_flight Requests currently being handled.\n");
+        out.push_str("# TYPE crud_multiversx_requests_in_flight gauge\n");
+        out.push_str(&format!(
+            "crud_multiversx_requests_in_flight {}\n",
+            self.in_flight.load(Ordering::Relaxed)
+        ));
+
+        out.push_str("# HELP crud_multiversx_persons Current number of stored persons.\n");
+        out.push_str("# TYPE crud_multiversx_persons gauge\n");
+        out.push_str(&format!("crud_multiversx_persons {}\n", storage.list().len()));
+
+        out.push_str("# HELP crud_multiversx_request_duration_seconds Request latency by route.\n");
+        out.push_str("# TYPE crud_multiversx_request_duration_seconds histogram\n");
+        for (route, histogram) in self.latency_by_route.lock().unwrap().iter() {
+            for (i, bound) in LATENCY_BUCKETS_SECONDS.iter().enumerate() {
+                out.push_str(&format!(
+                    "crud_multiversx_request_duration_seconds_bucket{{route=\"{}\",le=\"{}\"}} {}\n",
+                    route,
+                    bound,
+                    histogram.bucket_counts[i].load(Ordering::Relaxed)
+                ));
+            }
+            out.push_str(&format!(
+                "crud_multiversx_request_duration_seconds_bucket{{route=\"{}\",le=\"+Inf\"}} {}\n",
+                route,
+                histogram.bucket_counts[LATENCY_BUCKETS_SECONDS.len()].load(Ordering::Relaxed)
+            ));
+            out.push_str(&format!(
+                "crud_multiversx_request_duration_seconds_sum{{route=\"{}\"}} {}\n",
+                route,
+                histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            ));
+            out.push_str(&format!(
+                "crud_multiversx_request_duration_seconds_count{{route=\"{}\"}} {}\n",
+                route,
+                histogram.count.load(Ordering::Relaxed)
+            ));
+        }
+
+        out
+    }
+}
+
+/// Collapses `/persons/<id>` paths to a `/persons/:id` label so per-id
+/// traffic doesn't blow up metric cardinality.
+pub fn route_label(path: &str) -> &'static str {
+    match path {
+        "/persons" => "/persons",
+        "/persons/batch" => "/persons/batch",
+        "/persons/events" => "/persons/events",
+        "/metrics" => "/metrics",
+        path if path.starts_with("/persons/") && path.trim_start_matches("/persons/").parse::<u64>().is_ok() => {
+            "/persons/:id"
+        }
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,247 @@
+use crate::Person;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortField {
+    Name,
+    Age,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Parsed `?limit=&offset=&sort=&order=&min_age=&max_age=&name_contains=`
+/// query parameters for `GET /persons`.
+pub struct PersonQuery {
+    pub limit: Option<usize>,
+    pub offset: usize,
+    pub sort: Option<SortField>,
+    pub order: SortOrder,
+    pub min_age: Option<u32>,
+    pub max_age: Option<u32>,
+    pub name_contains: Option<String>,
+}
+
+impl PersonQuery {
+    pub fn parse(query: Option<&str>) -> Self {
+        let mut parsed = PersonQuery {
+            limit: None,
+            offset: 0,
+            sort: None,
+            order: SortOrder::Asc,
+            min_age: None,
+            max_age: None,
+            name_contains: None,
+        };
+
+        let Some(query) = query else { return parsed };
+        for pair in query.split('&') {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = percent_decode(parts.next().unwrap_or(""));
+
+            match key {
+                "limit" => parsed.limit = value.parse().ok(),
+                "offset" => parsed.offset = value.parse().unwrap_or(0),
+                "sort" => {
+                    parsed.sort = match value.as_str() {
+                        "name" => Some(SortField::Name),
+                        "age" => Some(SortField::Age),
+                        _ => None,
+                    }
+                }
+                "order" => {
+                    parsed.order = if value == "desc" { SortOrder::Desc } else { SortOrder::Asc };
+                }
+                "min_age" => parsed.min_age = value.parse().ok(),
+                "max_age" => parsed.max_age = value.parse().ok(),
+                "name_contains" => {
+                    parsed.name_contains = if value.is_empty() { None } else { Some(value) };
+                }
+                _ => {}
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Applies filtering, deterministic sorting, and pagination to `persons`,
+/// returning the requested page and the total match count (pre-pagination).
+pub fn apply(mut persons: Vec<Person>, query: &PersonQuery) -> (Vec<Person>, usize) {
+    persons.retain(|p| {
+        query.min_age.is_none_or(|min| p.age >= min)
+            && query.max_age.is_none_or(|max| p.age <= max)
+            && query
+                .name_contains
+                .as_ref()
+                .is_none_or(|needle| p.name.contains(needle.as_str()))
+    });
+
+    // Sort by id first so ordering is reproducible regardless of the
+    // storage backend's iteration order, then apply the requested sort.
+    persons.sort_by_key(|p| p.id);
+    if let Some(field) = query.sort {
+        persons.sort_by(|a, b| match field {
+            SortField::Name => a.name.cmp(&b.name),
+            SortField::Age => a.age.cmp(&b.age),
+        });
+        if query.order == SortOrder::Desc {
+            persons.reverse();
+        }
+    }
+
+    let total = persons.len();
+    let offset = query.offset.min(total);
+    let end = match query.limit {
+        Some(limit) => offset.saturating_add(limit).min(total),
+        None => total,
+    };
+    (persons[offset..end].to_vec(), total)
+}
+
+/// Minimal `application/x-www-form-urlencoded` decoder: `+` becomes a
+/// space and `%XX` escapes are unescaped to raw bytes, which are then
+/// decoded as UTF-8 once at the end (so multi-byte sequences like
+/// `%C3%A3` for `ã` round-trip correctly instead of being reassembled as
+/// one `char` per escaped byte).
+fn percent_decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '+' => bytes.push(b' '),
+            '%' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                    bytes.push(byte);
+                }
+            }
+            c => {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(id: u64, name: &str, age: u32) -> Person {
+        Person { id, name: name.to_string(), age }
+    }
+
+    #[test]
+    fn parse_defaults_when_query_is_absent() {
+        let query = PersonQuery::parse(None);
+        assert_eq!(query.limit, None);
+        assert_eq!(query.offset, 0);
+        assert!(query.sort.is_none());
+        assert!(query.order == SortOrder::Asc);
+        assert_eq!(query.min_age, None);
+        assert_eq!(query.max_age, None);
+        assert_eq!(query.name_contains, None);
+    }
+
+    #[test]
+    fn parse_reads_all_recognized_params() {
+        let query = PersonQuery::parse(Some(
+            "limit=10&offset=5&sort=age&order=desc&min_age=18&max_age=65&name_contains=ana",
+        ));
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.offset, 5);
+        assert!(query.sort == Some(SortField::Age));
+        assert!(query.order == SortOrder::Desc);
+        assert_eq!(query.min_age, Some(18));
+        assert_eq!(query.max_age, Some(65));
+        assert_eq!(query.name_contains, Some("ana".to_string()));
+    }
+
+    #[test]
+    fn parse_ignores_unknown_params_and_malformed_values() {
+        let query = PersonQuery::parse(Some("limit=not_a_number&bogus=1&sort=height"));
+        assert_eq!(query.limit, None);
+        assert!(query.sort.is_none());
+    }
+
+    #[test]
+    fn parse_percent_decodes_multibyte_name_contains() {
+        // "João" percent-encoded: %C3%A3 is the two-byte UTF-8 sequence for 'ã'.
+        let query = PersonQuery::parse(Some("name_contains=Jo%C3%A3o"));
+        assert_eq!(query.name_contains, Some("João".to_string()));
+    }
+
+    #[test]
+    fn apply_filters_by_age_range() {
+        let persons = vec![person(1, "Ana", 10), person(2, "Bia", 30), person(3, "Caio", 50)];
+        let query = PersonQuery::parse(Some("min_age=20&max_age=40"));
+
+        let (items, total) = apply(persons, &query);
+        assert_eq!(total, 1);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "Bia");
+    }
+
+    #[test]
+    fn apply_filters_by_name_contains() {
+        let persons = vec![person(1, "Ana", 10), person(2, "Bianca", 30), person(3, "Caio", 40)];
+        let query = PersonQuery::parse(Some("name_contains=an"));
+
+        let (items, _) = apply(persons, &query);
+        let names: Vec<_> = items.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Bianca"]);
+    }
+
+    #[test]
+    fn apply_sorts_descending_when_requested() {
+        let persons = vec![person(1, "Ana", 10), person(2, "Bia", 30), person(3, "Caio", 20)];
+        let query = PersonQuery::parse(Some("sort=age&order=desc"));
+
+        let (items, _) = apply(persons, &query);
+        let ages: Vec<_> = items.iter().map(|p| p.age).collect();
+        assert_eq!(ages, vec![30, 20, 10]);
+    }
+
+    #[test]
+    fn apply_is_ordered_by_id_when_no_sort_is_given() {
+        // Constructed out of id order to make sure `apply` itself imposes
+        // the deterministic order rather than relying on input order.
+        let persons = vec![person(3, "Caio", 20), person(1, "Ana", 10), person(2, "Bia", 30)];
+        let query = PersonQuery::parse(None);
+
+        let (items, _) = apply(persons, &query);
+        let ids: Vec<_> = items.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn apply_paginates_with_limit_and_offset() {
+        let persons = vec![
+            person(1, "Ana", 10),
+            person(2, "Bia", 20),
+            person(3, "Caio", 30),
+            person(4, "Davi", 40),
+        ];
+        let query = PersonQuery::parse(Some("limit=2&offset=1"));
+
+        let (items, total) = apply(persons, &query);
+        assert_eq!(total, 4);
+        let ids: Vec<_> = items.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn apply_offset_past_the_end_returns_an_empty_page() {
+        let persons = vec![person(1, "Ana", 10)];
+        let query = PersonQuery::parse(Some("offset=5"));
+
+        let (items, total) = apply(persons, &query);
+        assert_eq!(total, 1);
+        assert!(items.is_empty());
+    }
+}
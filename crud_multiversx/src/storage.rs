@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+
+use crate::batch::{BatchError, BatchOp, BatchOutcome};
+use crate::Person;
+
+/// Persistence backend for `Person` records. Implementations are
+/// responsible for their own ID assignment on `create`.
+pub trait Storage: Send + Sync {
+    fn create(&self, person: Person) -> Person;
+    fn get(&self, id: u64) -> Option<Person>;
+    fn list(&self) -> Vec<Person>;
+    fn update(&self, id: u64, data: Person) -> Option<Person>;
+    fn delete(&self, id: u64) -> bool;
+
+    /// Applies `ops` as a single transaction: if any operation is invalid
+    /// (an update/delete referencing a missing id), none of them take
+    /// effect.
+    fn apply_batch(&self, ops: &[BatchOp]) -> Result<Vec<BatchOutcome>, BatchError>;
+}
+
+/// The original `HashMap`-backed store. Data is lost on restart.
+pub struct InMemoryStorage {
+    persons: Mutex<HashMap<u64, Person>>,
+    next_id: AtomicU64,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        InMemoryStorage {
+            persons: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn create(&self, mut person: Person) -> Person {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        person.id = id;
+        self.persons.lock().unwrap().insert(id, person.clone());
+        person
+    }
+
+    fn get(&self, id: u64) -> Option<Person> {
+        self.persons.lock().unwrap().get(&id).cloned()
+    }
+
+    fn list(&self) -> Vec<Person> {
+        self.persons.lock().unwrap().values().cloned().collect()
+    }
+
+    fn update(&self, id: u64, data: Person) -> Option<Person> {
+        let mut persons = self.persons.lock().unwrap();
+        let person = persons.get_mut(&id)?;
+        person.name = data.name;
+        person.age = data.age;
+        Some(person.clone())
+    }
+
+    fn delete(&self, id: u64) -> bool {
+        self.persons.lock().unwrap().remove(&id).is_some()
+    }
+
+    fn apply_batch(&self, ops: &[BatchOp]) -> Result<Vec<BatchOutcome>, BatchError> {
+        let mut persons = self.persons.lock().unwrap();
+
+        // Validate before mutating anything, so a failure midway through
+        // the batch can't leave the store partially updated. An id deleted
+        // earlier in the batch counts as missing for later operations.
+        let mut deleted = HashSet::new();
+        for (index, op) in ops.iter().enumerate() {
+            let id = match op {
+                BatchOp::Update { id, .. } => *id,
+                BatchOp::Delete { id } => *id,
+                BatchOp::Create { .. } => continue,
+            };
+            if !persons.contains_key(&id) || deleted.contains(&id) {
+                return Err(BatchError {
+                    index,
+                    message: format!("pessoa {} não encontrada", id),
+                });
+            }
+            if let BatchOp::Delete { .. } = op {
+                deleted.insert(id);
+            }
+        }
+
+        let mut outcomes = Vec::with_capacity(ops.len());
+        for op in ops {
+            let outcome = match op {
+                BatchOp::Create { data } => {
+                    let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                    let mut person = data.clone();
+                    person.id = id;
+                    persons.insert(id, person.clone());
+                    BatchOutcome::Created { person }
+                }
+                BatchOp::Update { id, data } => {
+                    let person = persons.get_mut(id).expect("validado acima");
+                    person.name = data.name.clone();
+                    person.age = data.age;
+                    BatchOutcome::Updated { person: person.clone() }
+                }
+                BatchOp::Delete { id } => {
+                    persons.remove(id);
+                    BatchOutcome::Deleted { id: *id }
+                }
+            };
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+}
+
+/// SQLite-backed store. IDs are assigned by `AUTOINCREMENT` rather than
+/// computed from the existing rows, so concurrent creates can't collide.
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS persons (
+                id   INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                age  INTEGER NOT NULL
+            )",
+            (),
+        )?;
+        Ok(SqliteStorage { conn: Mutex::new(conn) })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn create(&self, mut person: Person) -> Person {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO persons (name, age) VALUES (?1, ?2)",
+            (&person.name, &person.age),
+        )
+        .expect("falha ao inserir pessoa");
+        person.id = conn.last_insert_rowid() as u64;
+        person
+    }
+
+    fn get(&self, id: u64) -> Option<Person> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, name, age FROM persons WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(Person {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    age: row.get(2)?,
+                })
+            },
+        )
+        .ok()
+    }
+
+    fn list(&self) -> Vec<Person> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, name, age FROM persons").unwrap();
+        stmt.query_map([], |row| {
+            Ok(Person {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                age: row.get(2)?,
+            })
+        })
+        .unwrap()
+        .filter_map(Result::ok)
+        .collect()
+    }
+
+    fn update(&self, id: u64, data: Person) -> Option<Person> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn
+            .execute(
+                "UPDATE persons SET name = ?1, age = ?2 WHERE id = ?3",
+                (&data.name, &data.age, id),
+            )
+            .expect("falha ao atualizar pessoa");
+        if rows == 0 {
+            return None;
+        }
+        conn.query_row(
+            "SELECT id, name, age FROM persons WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(Person {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    age: row.get(2)?,
+                })
+            },
+        )
+        .ok()
+    }
+
+    fn delete(&self, id: u64) -> bool {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM persons WHERE id = ?1", [id])
+            .expect("falha ao remover pessoa")
+            > 0
+    }
+
+    fn apply_batch(&self, ops: &[BatchOp]) -> Result<Vec<BatchOutcome>, BatchError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().expect("falha ao iniciar transação");
+        let mut outcomes = Vec::with_capacity(ops.len());
+
+        for (index, op) in ops.iter().enumerate() {
+            let outcome = match op {
+                BatchOp::Create { data } => {
+                    tx.execute(
+                        "INSERT INTO persons (name, age) VALUES (?1, ?2)",
+                        (&data.name, &data.age),
+                    )
+                    .expect("falha ao inserir pessoa");
+                    let id = tx.last_insert_rowid() as u64;
+                    BatchOutcome::Created {
+                        person: Person { id, name: data.name.clone(), age: data.age },
+                    }
+                }
+                BatchOp::Update { id, data } => {
+                    let rows = tx
+                        .execute(
+                            "UPDATE persons SET name = ?1, age = ?2 WHERE id = ?3",
+                            (&data.name, &data.age, id),
+                        )
+                        .expect("falha ao atualizar pessoa");
+                    if rows == 0 {
+                        // Dropping `tx` without committing rolls back
+                        // everything applied so far in this batch.
+                        return Err(BatchError {
+                            index,
+                            message: format!("pessoa {} não encontrada", id),
+                        });
+                    }
+                    BatchOutcome::Updated {
+                        person: Person { id: *id, name: data.name.clone(), age: data.age },
+                    }
+                }
+                BatchOp::Delete { id } => {
+                    let rows = tx
+                        .execute("DELETE FROM persons WHERE id = ?1", [id])
+                        .expect("falha ao remover pessoa");
+                    if rows == 0 {
+                        return Err(BatchError {
+                            index,
+                            message: format!("pessoa {} não encontrada", id),
+                        });
+                    }
+                    BatchOutcome::Deleted { id: *id }
+                }
+            };
+            outcomes.push(outcome);
+        }
+
+        tx.commit().expect("falha ao commitar transação");
+        Ok(outcomes)
+    }
+}
+
+/// Picks a backend from the `STORAGE_BACKEND` env var (`sqlite` or
+/// `memory`), defaulting to `sqlite` so data survives a restart. The
+/// SQLite path is read from `STORAGE_SQLITE_PATH`, defaulting to
+/// `persons.db`.
+pub fn from_env() -> Box<dyn Storage> {
+    match std::env::var("STORAGE_BACKEND").as_deref() {
+        Ok("memory") => Box::new(InMemoryStorage::new()),
+        _ => {
+            let path = std::env::var("STORAGE_SQLITE_PATH")
+                .unwrap_or_else(|_| "persons.db".to_string());
+            Box::new(SqliteStorage::open(&path).expect("falha ao abrir banco sqlite"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person(name: &str, age: u32) -> Person {
+        Person { id: 0, name: name.to_string(), age }
+    }
+
+    /// Both backends must agree on basic CRUD behavior.
+    fn assert_crud_roundtrip(storage: &dyn Storage) {
+        let created = storage.create(person("Ana", 30));
+        assert_eq!(storage.get(created.id).unwrap().name, "Ana");
+        assert_eq!(storage.list().len(), 1);
+
+        let updated = storage.update(created.id, person("Ana Paula", 31)).unwrap();
+        assert_eq!(updated.name, "Ana Paula");
+        assert_eq!(updated.age, 31);
+
+        assert!(storage.delete(created.id));
+        assert!(storage.get(created.id).is_none());
+    }
+
+    #[test]
+    fn in_memory_storage_crud_roundtrip() {
+        assert_crud_roundtrip(&InMemoryStorage::new());
+    }
+
+    #[test]
+    fn sqlite_storage_crud_roundtrip() {
+        assert_crud_roundtrip(&SqliteStorage::open(":memory:").unwrap());
+    }
+
+    /// A batch with an invalid operation (update/delete of a missing id)
+    /// must leave the store exactly as it was before the batch ran.
+    fn assert_batch_rolls_back_on_missing_id(storage: &dyn Storage) {
+        let existing = storage.create(person("Bia", 20));
+
+        let ops = vec![
+            BatchOp::Create { data: person("Carlos", 40) },
+            BatchOp::Update { id: existing.id, data: person("Bia Costa", 21) },
+            BatchOp::Delete { id: 9999 },
+        ];
+
+        let err = storage.apply_batch(&ops).unwrap_err();
+        assert_eq!(err.index, 2);
+
+        assert_eq!(storage.list().len(), 1);
+        assert_eq!(storage.get(existing.id).unwrap().name, "Bia");
+    }
+
+    #[test]
+    fn in_memory_storage_batch_rolls_back_on_missing_id() {
+        assert_batch_rolls_back_on_missing_id(&InMemoryStorage::new());
+    }
+
+    #[test]
+    fn sqlite_storage_batch_rolls_back_on_missing_id() {
+        assert_batch_rolls_back_on_missing_id(&SqliteStorage::open(":memory:").unwrap());
+    }
+
+    #[test]
+    fn in_memory_storage_batch_applies_all_ops_when_valid() {
+        let storage = InMemoryStorage::new();
+        let ops = vec![
+            BatchOp::Create { data: person("Davi", 25) },
+            BatchOp::Create { data: person("Eva", 35) },
+        ];
+
+        let outcomes = storage.apply_batch(&ops).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(storage.list().len(), 2);
+    }
+}